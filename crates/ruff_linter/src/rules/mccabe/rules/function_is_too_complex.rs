@@ -1,8 +1,10 @@
-use ruff_python_ast::{self as ast, ExceptHandler, Stmt, Expr};
+use indexmap::IndexSet;
+use ruff_python_ast::{self as ast, ExceptHandler, Expr, ExprContext, Stmt};
 
-use ruff_diagnostics::{Diagnostic, Violation};
+use ruff_diagnostics::{Diagnostic, Edit, Fix, Violation};
 use ruff_macros::{derive_message_formats, ViolationMetadata};
-use ruff_text_size::Ranged;
+use ruff_source_file::Locator;
+use ruff_text_size::{Ranged, TextRange};
 
 /// ## What it does
 /// Checks for functions with a high complexity.
@@ -25,31 +27,1239 @@ impl Violation for ComplexStructure {
     }
 }
 
-/// Get the complexity contribution from a logical expression by counting transitions
-/// between different boolean operators (and/or).
+/// Get the complexity contribution from an expression, counting:
+/// - transitions between different boolean operators (and/or) in any `BoolOp` chain;
+/// - each `if` clause of a conditional (ternary) expression;
+/// - each `if` clause in a comprehension (`ListComp`/`SetComp`/`DictComp`/`GeneratorExp`);
+/// - `lambda` bodies, traversed like nested function bodies.
+///
+/// The boolean-operator and conditional-expression checks run on tests found anywhere
+/// in the expression tree (return values, assignment right-hand sides, call arguments,
+/// comprehension guards, etc.), not just in `if`/`while` tests.
 fn get_expression_complexity(expr: &Expr) -> usize {
     use ruff_python_ast::BoolOp;
-    
-    fn count_bool_op_sequences(expr: &Expr, current_op_kind: Option<&BoolOp>, mut count: usize) -> usize {
+
+    fn count_bool_op_sequences(
+        expr: &Expr,
+        current_op_kind: Option<&BoolOp>,
+        mut count: usize,
+    ) -> usize {
         if let Expr::BoolOp(ast::ExprBoolOp { op, values, .. }) = expr {
             // If this is a different operator than the current one, increment count
-            if current_op_kind.is_none() || core::mem::discriminant(current_op_kind.unwrap()) != core::mem::discriminant(op) {
+            if current_op_kind.is_none()
+                || core::mem::discriminant(current_op_kind.unwrap()) != core::mem::discriminant(op)
+            {
                 count += 1;
             }
-            
-            // Recursively check values with the current operator kind
-            for value in values {
-                count = count_bool_op_sequences(value, Some(op), count);
+
+            // Recursively check values with the current operator kind
+            for value in values {
+                count = count_bool_op_sequences(value, Some(op), count);
+            }
+        }
+
+        count
+    }
+
+    // Look for further branching nested beneath a `BoolOp` chain, without re-counting
+    // the chain itself (that's already handled by `count_bool_op_sequences`).
+    fn get_nested_complexity(expr: &Expr) -> usize {
+        if let Expr::BoolOp(ast::ExprBoolOp { values, .. }) = expr {
+            values.iter().map(get_nested_complexity).sum()
+        } else {
+            get_expression_complexity(expr)
+        }
+    }
+
+    match expr {
+        Expr::BoolOp(ast::ExprBoolOp { values, .. }) => {
+            count_bool_op_sequences(expr, None, 0)
+                + values.iter().map(get_nested_complexity).sum::<usize>()
+        }
+        Expr::IfExp(ast::ExprIfExp {
+            test, body, orelse, ..
+        }) => {
+            1 + get_expression_complexity(test)
+                + get_expression_complexity(body)
+                + get_expression_complexity(orelse)
+        }
+        Expr::ListComp(ast::ExprListComp {
+            elt, generators, ..
+        })
+        | Expr::SetComp(ast::ExprSetComp {
+            elt, generators, ..
+        })
+        | Expr::GeneratorExp(ast::ExprGenerator {
+            elt, generators, ..
+        }) => get_expression_complexity(elt) + get_comprehension_complexity(generators),
+        Expr::DictComp(ast::ExprDictComp {
+            key,
+            value,
+            generators,
+            ..
+        }) => {
+            get_expression_complexity(key)
+                + get_expression_complexity(value)
+                + get_comprehension_complexity(generators)
+        }
+        Expr::Lambda(ast::ExprLambda { body, .. }) => get_expression_complexity(body),
+        Expr::Call(ast::ExprCall {
+            func, arguments, ..
+        }) => {
+            get_expression_complexity(func)
+                + arguments
+                    .args
+                    .iter()
+                    .chain(arguments.keywords.iter().map(|keyword| &keyword.value))
+                    .map(get_expression_complexity)
+                    .sum::<usize>()
+        }
+        Expr::Named(ast::ExprNamed { value, .. })
+        | Expr::Starred(ast::ExprStarred { value, .. })
+        | Expr::Await(ast::ExprAwait { value, .. })
+        | Expr::YieldFrom(ast::ExprYieldFrom { value, .. }) => get_expression_complexity(value),
+        Expr::Yield(ast::ExprYield { value, .. }) => {
+            value.as_deref().map_or(0, get_expression_complexity)
+        }
+        Expr::BinOp(ast::ExprBinOp { left, right, .. }) => {
+            get_expression_complexity(left) + get_expression_complexity(right)
+        }
+        Expr::UnaryOp(ast::ExprUnaryOp { operand, .. }) => get_expression_complexity(operand),
+        Expr::Compare(ast::ExprCompare {
+            left, comparators, ..
+        }) => {
+            get_expression_complexity(left)
+                + comparators
+                    .iter()
+                    .map(get_expression_complexity)
+                    .sum::<usize>()
+        }
+        Expr::Tuple(ast::ExprTuple { elts, .. })
+        | Expr::List(ast::ExprList { elts, .. })
+        | Expr::Set(ast::ExprSet { elts, .. }) => elts.iter().map(get_expression_complexity).sum(),
+        _ => 0,
+    }
+}
+
+/// Get the complexity contribution of the `if` clauses across a comprehension's
+/// `for` generators.
+fn get_comprehension_complexity(generators: &[ast::Comprehension]) -> usize {
+    generators
+        .iter()
+        .flat_map(|generator| &generator.ifs)
+        .map(|if_clause| 1 + get_expression_complexity(if_clause))
+        .sum()
+}
+
+/// Determines how a `match` statement's `case` arms contribute to cyclomatic
+/// complexity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum MatchComplexityStrategy {
+    /// `+1` for the `match` itself, plus `+1` for each irrefutable (catch-all)
+    /// pattern. Under either strategy, each extra `|` alternative within a pattern
+    /// adds a further `+1`.
+    #[default]
+    Legacy,
+    /// Score each `case` (other than a trailing bare wildcard `_`) the way `elif`
+    /// branches are scored in `Stmt::If`, so that multi-arm `match` dispatch is
+    /// weighted the same as an equivalent `if`/`elif` chain.
+    PerArm,
+}
+
+/// Returns `true` if `pattern` is a bare wildcard (`_`), as opposed to a named
+/// capture pattern, which binds a name and so reads differently to a reviewer even
+/// though both are irrefutable.
+fn is_bare_wildcard(pattern: &ast::Pattern) -> bool {
+    matches!(
+        pattern,
+        ast::Pattern::MatchAs(ast::PatternMatchAs {
+            pattern: None,
+            name: None,
+            ..
+        })
+    )
+}
+
+/// Count the `|` alternations within a single pattern as additional branches, one
+/// per extra alternative beyond the first.
+fn count_pattern_alternations(pattern: &ast::Pattern) -> usize {
+    if let ast::Pattern::MatchOr(ast::PatternMatchOr { patterns, .. }) = pattern {
+        patterns.len().saturating_sub(1)
+    } else {
+        0
+    }
+}
+
+fn get_complexity_number(stmts: &[Stmt], match_complexity: MatchComplexityStrategy) -> usize {
+    let mut complexity = 0;
+    for stmt in stmts {
+        match stmt {
+            Stmt::If(ast::StmtIf {
+                test,
+                body,
+                elif_else_clauses,
+                ..
+            }) => {
+                complexity += 1;
+                complexity += get_expression_complexity(test);
+                complexity += get_complexity_number(body, match_complexity);
+
+                for clause in elif_else_clauses {
+                    complexity += 1;
+
+                    if let Some(test) = &clause.test {
+                        complexity += get_expression_complexity(test);
+                    }
+
+                    complexity += get_complexity_number(&clause.body, match_complexity);
+                }
+            }
+            Stmt::For(ast::StmtFor { body, orelse, .. }) => {
+                complexity += 1;
+                complexity += get_complexity_number(body, match_complexity);
+
+                if !orelse.is_empty() {
+                    complexity += 1;
+                }
+
+                complexity += get_complexity_number(orelse, match_complexity);
+            }
+            Stmt::With(ast::StmtWith { body, .. }) => {
+                complexity += get_complexity_number(body, match_complexity);
+            }
+            Stmt::While(ast::StmtWhile {
+                test, body, orelse, ..
+            }) => {
+                complexity += 1;
+                complexity += get_expression_complexity(test);
+                complexity += get_complexity_number(body, match_complexity);
+
+                if !orelse.is_empty() {
+                    complexity += 1;
+                }
+
+                complexity += get_complexity_number(orelse, match_complexity);
+            }
+            Stmt::Match(ast::StmtMatch { cases, .. }) => {
+                complexity += 1;
+
+                for (index, case) in cases.iter().enumerate() {
+                    complexity += count_pattern_alternations(&case.pattern);
+
+                    match match_complexity {
+                        MatchComplexityStrategy::Legacy => {
+                            if case.pattern.is_irrefutable() {
+                                complexity += 1;
+                            }
+                        }
+                        MatchComplexityStrategy::PerArm => {
+                            let is_trailing_wildcard = index == cases.len() - 1
+                                && case.guard.is_none()
+                                && is_bare_wildcard(&case.pattern);
+                            if !is_trailing_wildcard {
+                                complexity += 1;
+                            }
+                        }
+                    }
+
+                    if let Some(guard) = &case.guard {
+                        complexity += get_expression_complexity(guard);
+                    }
+
+                    complexity += get_complexity_number(&case.body, match_complexity);
+                }
+            }
+            Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                complexity += get_complexity_number(body, match_complexity);
+
+                if !handlers.is_empty() {
+                    complexity += 1;
+                }
+
+                if !orelse.is_empty() {
+                    complexity += 1;
+                }
+
+                // Process the bodies of all handlers and clauses
+                complexity += get_complexity_number(orelse, match_complexity);
+                complexity += get_complexity_number(finalbody, match_complexity);
+
+                for handler in handlers {
+                    let ExceptHandler::ExceptHandler(ast::ExceptHandlerExceptHandler {
+                        body, ..
+                    }) = handler;
+                    complexity += get_complexity_number(body, match_complexity);
+                }
+            }
+            Stmt::FunctionDef(ast::StmtFunctionDef { body, .. }) => {
+                complexity += get_complexity_number(body, match_complexity);
+            }
+            Stmt::ClassDef(ast::StmtClassDef { body, .. }) => {
+                complexity += get_complexity_number(body, match_complexity);
+            }
+            Stmt::Return(ast::StmtReturn {
+                value: Some(value), ..
+            }) => {
+                complexity += get_expression_complexity(value);
+            }
+            Stmt::Assign(ast::StmtAssign { value, .. }) => {
+                complexity += get_expression_complexity(value);
+            }
+            Stmt::AugAssign(ast::StmtAugAssign { value, .. }) => {
+                complexity += get_expression_complexity(value);
+            }
+            Stmt::AnnAssign(ast::StmtAnnAssign {
+                value: Some(value), ..
+            }) => {
+                complexity += get_expression_complexity(value);
+            }
+            Stmt::Expr(ast::StmtExpr { value, .. }) => {
+                complexity += get_expression_complexity(value);
+            }
+            _ => {}
+        }
+    }
+    complexity
+}
+
+pub(crate) fn function_is_too_complex(
+    stmt: &Stmt,
+    name: &str,
+    body: &[Stmt],
+    max_complexity: usize,
+    match_complexity: MatchComplexityStrategy,
+    locator: &Locator,
+) -> Option<Diagnostic> {
+    let complexity = get_complexity_number(body, match_complexity) + 1;
+    if complexity > max_complexity {
+        let mut diagnostic = Diagnostic::new(
+            ComplexStructure {
+                name: name.to_string(),
+                complexity,
+                max_complexity,
+            },
+            stmt.range(),
+        );
+        if let Some(fix) = extract_block_fix(stmt, name, body, locator) {
+            diagnostic.set_fix(fix);
+        }
+        Some(diagnostic)
+    } else {
+        None
+    }
+}
+
+/// Find the single most-nested contiguous block of statements within `stmts` (e.g.
+/// the body of the deepest `if`/`for`/`while`), returning its nesting depth alongside
+/// the block itself. Ties are broken in favor of the first block encountered at the
+/// deepest level.
+fn find_most_nested_block(stmts: &[Stmt], depth: usize) -> Option<(usize, &[Stmt])> {
+    let mut best: Option<(usize, &[Stmt])> = None;
+
+    let mut consider = |child_body: &'_ [Stmt]| {
+        let candidate =
+            find_most_nested_block(child_body, depth + 1).unwrap_or((depth + 1, child_body));
+        if best.is_none_or(|(best_depth, _)| candidate.0 > best_depth) {
+            best = Some(candidate);
+        }
+    };
+
+    for stmt in stmts {
+        match stmt {
+            Stmt::If(ast::StmtIf {
+                body,
+                elif_else_clauses,
+                ..
+            }) => {
+                consider(body);
+                for clause in elif_else_clauses {
+                    consider(&clause.body);
+                }
+            }
+            Stmt::For(ast::StmtFor { body, .. }) | Stmt::While(ast::StmtWhile { body, .. }) => {
+                consider(body);
+            }
+            _ => {}
+        }
+    }
+
+    best
+}
+
+/// Record every `Name` read or written within `expr` into `reads`/`writes`, used to
+/// determine the parameters (reads) and return values (writes) of an extracted block.
+fn collect_name_refs(expr: &Expr, reads: &mut IndexSet<String>, writes: &mut IndexSet<String>) {
+    match expr {
+        Expr::Name(ast::ExprName { id, ctx, .. }) => match ctx {
+            ExprContext::Load => {
+                reads.insert(id.to_string());
+            }
+            ExprContext::Store | ExprContext::Del => {
+                writes.insert(id.to_string());
+            }
+            ExprContext::Invalid => {}
+        },
+        Expr::BoolOp(ast::ExprBoolOp { values, .. }) => {
+            for value in values {
+                collect_name_refs(value, reads, writes);
+            }
+        }
+        Expr::BinOp(ast::ExprBinOp { left, right, .. }) => {
+            collect_name_refs(left, reads, writes);
+            collect_name_refs(right, reads, writes);
+        }
+        Expr::UnaryOp(ast::ExprUnaryOp { operand, .. }) => {
+            collect_name_refs(operand, reads, writes);
+        }
+        Expr::IfExp(ast::ExprIfExp {
+            test, body, orelse, ..
+        }) => {
+            collect_name_refs(test, reads, writes);
+            collect_name_refs(body, reads, writes);
+            collect_name_refs(orelse, reads, writes);
+        }
+        Expr::Compare(ast::ExprCompare {
+            left, comparators, ..
+        }) => {
+            collect_name_refs(left, reads, writes);
+            for comparator in comparators {
+                collect_name_refs(comparator, reads, writes);
+            }
+        }
+        Expr::Call(ast::ExprCall {
+            func, arguments, ..
+        }) => {
+            collect_name_refs(func, reads, writes);
+            for arg in &arguments.args {
+                collect_name_refs(arg, reads, writes);
+            }
+            for keyword in &arguments.keywords {
+                collect_name_refs(&keyword.value, reads, writes);
+            }
+        }
+        Expr::Attribute(ast::ExprAttribute { value, .. }) => {
+            collect_name_refs(value, reads, writes);
+        }
+        Expr::Subscript(ast::ExprSubscript { value, slice, .. }) => {
+            collect_name_refs(value, reads, writes);
+            collect_name_refs(slice, reads, writes);
+        }
+        Expr::Starred(ast::ExprStarred { value, .. })
+        | Expr::Await(ast::ExprAwait { value, .. })
+        | Expr::YieldFrom(ast::ExprYieldFrom { value, .. })
+        | Expr::Named(ast::ExprNamed { value, .. }) => {
+            collect_name_refs(value, reads, writes);
+        }
+        Expr::Yield(ast::ExprYield { value, .. }) => {
+            if let Some(value) = value {
+                collect_name_refs(value, reads, writes);
+            }
+        }
+        Expr::Tuple(ast::ExprTuple { elts, .. })
+        | Expr::List(ast::ExprList { elts, .. })
+        | Expr::Set(ast::ExprSet { elts, .. }) => {
+            for elt in elts {
+                collect_name_refs(elt, reads, writes);
+            }
+        }
+        Expr::Dict(ast::ExprDict { items, .. }) => {
+            for item in items {
+                if let Some(key) = &item.key {
+                    collect_name_refs(key, reads, writes);
+                }
+                collect_name_refs(&item.value, reads, writes);
+            }
+        }
+        Expr::Slice(ast::ExprSlice {
+            lower, upper, step, ..
+        }) => {
+            for part in [lower, upper, step].into_iter().flatten() {
+                collect_name_refs(part, reads, writes);
+            }
+        }
+        Expr::FString(ast::ExprFString { value, .. }) => {
+            for part in value {
+                if let ast::FStringPart::FString(f_string) = part {
+                    for element in &f_string.elements {
+                        if let ast::FStringElement::Expression(expression) = element {
+                            collect_name_refs(&expression.expression, reads, writes);
+                        }
+                    }
+                }
+            }
+        }
+        Expr::Lambda(ast::ExprLambda { body, .. }) => {
+            collect_name_refs(body, reads, writes);
+        }
+        Expr::ListComp(ast::ExprListComp {
+            elt, generators, ..
+        })
+        | Expr::SetComp(ast::ExprSetComp {
+            elt, generators, ..
+        })
+        | Expr::GeneratorExp(ast::ExprGenerator {
+            elt, generators, ..
+        }) => {
+            collect_name_refs(elt, reads, writes);
+            collect_comprehension_name_refs(generators, reads, writes);
+        }
+        Expr::DictComp(ast::ExprDictComp {
+            key,
+            value,
+            generators,
+            ..
+        }) => {
+            collect_name_refs(key, reads, writes);
+            collect_name_refs(value, reads, writes);
+            collect_comprehension_name_refs(generators, reads, writes);
+        }
+        _ => {}
+    }
+}
+
+/// Record the names read (in the `for`/`if` clauses) and bound (by the `for` targets)
+/// across a comprehension's generators.
+fn collect_comprehension_name_refs(
+    generators: &[ast::Comprehension],
+    reads: &mut IndexSet<String>,
+    writes: &mut IndexSet<String>,
+) {
+    for generator in generators {
+        collect_name_refs(&generator.target, reads, writes);
+        collect_name_refs(&generator.iter, reads, writes);
+        for if_clause in &generator.ifs {
+            collect_name_refs(if_clause, reads, writes);
+        }
+    }
+}
+
+/// Record the names read and written across a block of statements.
+fn collect_block_name_refs(
+    stmts: &[Stmt],
+    reads: &mut IndexSet<String>,
+    writes: &mut IndexSet<String>,
+) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::Expr(ast::StmtExpr { value, .. }) => collect_name_refs(value, reads, writes),
+            Stmt::Return(ast::StmtReturn {
+                value: Some(value), ..
+            }) => {
+                collect_name_refs(value, reads, writes);
+            }
+            Stmt::Assign(ast::StmtAssign { targets, value, .. }) => {
+                collect_name_refs(value, reads, writes);
+                for target in targets {
+                    collect_name_refs(target, reads, writes);
+                }
+            }
+            Stmt::AugAssign(ast::StmtAugAssign { target, value, .. }) => {
+                // An aug-assign target is read before it's written (`x += 1` reads
+                // `x`), but its `ExprContext` is `Store`, so `collect_name_refs`
+                // alone would only ever record it as a write.
+                if let Expr::Name(ast::ExprName { id, .. }) = target.as_ref() {
+                    reads.insert(id.to_string());
+                    writes.insert(id.to_string());
+                } else {
+                    collect_name_refs(target, reads, writes);
+                }
+                collect_name_refs(value, reads, writes);
+            }
+            Stmt::AnnAssign(ast::StmtAnnAssign {
+                target,
+                value: Some(value),
+                ..
+            }) => {
+                collect_name_refs(target, reads, writes);
+                collect_name_refs(value, reads, writes);
+            }
+            Stmt::If(ast::StmtIf {
+                test,
+                body,
+                elif_else_clauses,
+                ..
+            }) => {
+                collect_name_refs(test, reads, writes);
+                collect_block_name_refs(body, reads, writes);
+                for clause in elif_else_clauses {
+                    if let Some(test) = &clause.test {
+                        collect_name_refs(test, reads, writes);
+                    }
+                    collect_block_name_refs(&clause.body, reads, writes);
+                }
+            }
+            Stmt::For(ast::StmtFor {
+                target,
+                iter,
+                body,
+                orelse,
+                ..
+            }) => {
+                collect_name_refs(target, reads, writes);
+                collect_name_refs(iter, reads, writes);
+                collect_block_name_refs(body, reads, writes);
+                collect_block_name_refs(orelse, reads, writes);
+            }
+            Stmt::While(ast::StmtWhile {
+                test, body, orelse, ..
+            }) => {
+                collect_name_refs(test, reads, writes);
+                collect_block_name_refs(body, reads, writes);
+                collect_block_name_refs(orelse, reads, writes);
+            }
+            Stmt::With(ast::StmtWith { items, body, .. }) => {
+                for item in items {
+                    collect_name_refs(&item.context_expr, reads, writes);
+                    if let Some(target) = &item.optional_vars {
+                        collect_name_refs(target, reads, writes);
+                    }
+                }
+                collect_block_name_refs(body, reads, writes);
+            }
+            Stmt::FunctionDef(ast::StmtFunctionDef { name, body, .. })
+            | Stmt::ClassDef(ast::StmtClassDef { name, body, .. }) => {
+                // Nested defs don't contribute free variables to the enclosing block,
+                // but their names still occupy the enclosing function's namespace.
+                writes.insert(name.to_string());
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Returns `true` if an `await` expression appears anywhere within `stmts`, meaning
+/// any function the block is extracted into must itself be declared `async`.
+fn block_awaits(stmts: &[Stmt]) -> bool {
+    fn expr_awaits(expr: &Expr) -> bool {
+        match expr {
+            Expr::Await(_) => true,
+            Expr::BoolOp(ast::ExprBoolOp { values, .. }) => values.iter().any(expr_awaits),
+            Expr::BinOp(ast::ExprBinOp { left, right, .. }) => {
+                expr_awaits(left) || expr_awaits(right)
+            }
+            Expr::IfExp(ast::ExprIfExp {
+                test, body, orelse, ..
+            }) => expr_awaits(test) || expr_awaits(body) || expr_awaits(orelse),
+            Expr::Call(ast::ExprCall {
+                func, arguments, ..
+            }) => {
+                expr_awaits(func)
+                    || arguments.args.iter().any(expr_awaits)
+                    || arguments.keywords.iter().any(|kw| expr_awaits(&kw.value))
+            }
+            _ => false,
+        }
+    }
+
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::Expr(ast::StmtExpr { value, .. }) => expr_awaits(value),
+        Stmt::Return(ast::StmtReturn {
+            value: Some(value), ..
+        }) => expr_awaits(value),
+        Stmt::Assign(ast::StmtAssign { value, .. }) => expr_awaits(value),
+        Stmt::If(ast::StmtIf {
+            test,
+            body,
+            elif_else_clauses,
+            ..
+        }) => {
+            expr_awaits(test)
+                || block_awaits(body)
+                || elif_else_clauses
+                    .iter()
+                    .any(|clause| block_awaits(&clause.body))
+        }
+        Stmt::For(ast::StmtFor {
+            is_async,
+            body,
+            orelse,
+            ..
+        }) => *is_async || block_awaits(body) || block_awaits(orelse),
+        Stmt::While(ast::StmtWhile {
+            test, body, orelse, ..
+        }) => expr_awaits(test) || block_awaits(body) || block_awaits(orelse),
+        Stmt::With(ast::StmtWith { is_async, body, .. }) => *is_async || block_awaits(body),
+        _ => false,
+    })
+}
+
+/// Returns `true` if `stmts` contains a `return`, `break`, `continue`, or
+/// `yield`/`yield from` anywhere in its own control flow (not inside a nested
+/// function or class definition, which have their own). Extracting a block
+/// containing any of these into a standalone helper would silently change the
+/// enclosing function's control flow, so callers must bail out rather than offer
+/// a fix in that case.
+fn block_has_unsupported_control_flow(stmts: &[Stmt]) -> bool {
+    fn expr_yields(expr: &Expr) -> bool {
+        match expr {
+            Expr::Yield(_) | Expr::YieldFrom(_) => true,
+            Expr::BoolOp(ast::ExprBoolOp { values, .. }) => values.iter().any(expr_yields),
+            Expr::BinOp(ast::ExprBinOp { left, right, .. }) => {
+                expr_yields(left) || expr_yields(right)
+            }
+            Expr::IfExp(ast::ExprIfExp {
+                test, body, orelse, ..
+            }) => expr_yields(test) || expr_yields(body) || expr_yields(orelse),
+            Expr::Call(ast::ExprCall {
+                func, arguments, ..
+            }) => {
+                expr_yields(func)
+                    || arguments.args.iter().any(expr_yields)
+                    || arguments.keywords.iter().any(|kw| expr_yields(&kw.value))
+            }
+            _ => false,
+        }
+    }
+
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_) => true,
+        Stmt::Expr(ast::StmtExpr { value, .. }) => expr_yields(value),
+        Stmt::Assign(ast::StmtAssign { value, .. }) => expr_yields(value),
+        Stmt::AugAssign(ast::StmtAugAssign { value, .. }) => expr_yields(value),
+        Stmt::AnnAssign(ast::StmtAnnAssign {
+            value: Some(value), ..
+        }) => expr_yields(value),
+        Stmt::If(ast::StmtIf {
+            test,
+            body,
+            elif_else_clauses,
+            ..
+        }) => {
+            expr_yields(test)
+                || block_has_unsupported_control_flow(body)
+                || elif_else_clauses.iter().any(|clause| {
+                    clause.test.as_ref().is_some_and(expr_yields)
+                        || block_has_unsupported_control_flow(&clause.body)
+                })
+        }
+        Stmt::For(ast::StmtFor {
+            iter, body, orelse, ..
+        }) => {
+            expr_yields(iter)
+                || block_has_unsupported_control_flow(body)
+                || block_has_unsupported_control_flow(orelse)
+        }
+        Stmt::While(ast::StmtWhile {
+            test, body, orelse, ..
+        }) => {
+            expr_yields(test)
+                || block_has_unsupported_control_flow(body)
+                || block_has_unsupported_control_flow(orelse)
+        }
+        Stmt::With(ast::StmtWith { body, .. }) => block_has_unsupported_control_flow(body),
+        Stmt::Try(ast::StmtTry {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+            ..
+        }) => {
+            block_has_unsupported_control_flow(body)
+                || block_has_unsupported_control_flow(orelse)
+                || block_has_unsupported_control_flow(finalbody)
+                || handlers.iter().any(|handler| {
+                    let ExceptHandler::ExceptHandler(ast::ExceptHandlerExceptHandler {
+                        body, ..
+                    }) = handler;
+                    block_has_unsupported_control_flow(body)
+                })
+        }
+        Stmt::Match(ast::StmtMatch { cases, .. }) => cases
+            .iter()
+            .any(|case| block_has_unsupported_control_flow(&case.body)),
+        _ => false,
+    })
+}
+
+/// Re-indent a block of source lines, stripping the common leading whitespace shared
+/// by every line and replacing it with `new_indent`.
+fn reindent(source: &str, new_indent: &str) -> String {
+    let common_indent = source
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    source
+        .lines()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{new_indent}{}", &line[common_indent.min(line.len())..])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Record the names that `stmts` reads before any local assignment establishes them,
+/// pushing each into `params` the first time it's seen. `bound` is the set of names
+/// already bound on entry (e.g. from statements preceding `stmts` in the same scope);
+/// returns the set of names guaranteed to be bound after `stmts` runs to completion,
+/// so callers can thread binding state through a sequence of sibling statements.
+///
+/// A name is only carried forward as "guaranteed bound" across a conditional (an
+/// `if` without a trailing `else`, a `for`/`while` body, a `try` body) if every
+/// branch is certain to run; otherwise a later read of that name is conservatively
+/// treated as needing the original value passed in, which is what makes the
+/// accumulator pattern (`total = total + item`) surface `total` as both a parameter
+/// and a return value instead of silently dropping it from the parameter list.
+fn scan_block_bindings(
+    stmts: &[Stmt],
+    bound: &IndexSet<String>,
+    params: &mut IndexSet<String>,
+) -> IndexSet<String> {
+    fn record(expr: &Expr, bound: &mut IndexSet<String>, params: &mut IndexSet<String>) {
+        let mut reads = IndexSet::default();
+        let mut writes = IndexSet::default();
+        collect_name_refs(expr, &mut reads, &mut writes);
+        for read in reads {
+            if bound.insert(read.clone()) {
+                params.insert(read);
+            }
+        }
+        for write in writes {
+            bound.insert(write);
+        }
+    }
+
+    let mut bound = bound.clone();
+    for stmt in stmts {
+        match stmt {
+            Stmt::Expr(ast::StmtExpr { value, .. }) => record(value, &mut bound, params),
+            Stmt::Return(ast::StmtReturn {
+                value: Some(value), ..
+            }) => record(value, &mut bound, params),
+            Stmt::Assign(ast::StmtAssign { targets, value, .. }) => {
+                record(value, &mut bound, params);
+                for target in targets {
+                    record(target, &mut bound, params);
+                }
+            }
+            Stmt::AugAssign(ast::StmtAugAssign { target, value, .. }) => {
+                record(target, &mut bound, params);
+                record(value, &mut bound, params);
+                record(target, &mut bound, params);
+            }
+            Stmt::AnnAssign(ast::StmtAnnAssign {
+                target,
+                value: Some(value),
+                ..
+            }) => {
+                record(value, &mut bound, params);
+                record(target, &mut bound, params);
+            }
+            Stmt::If(ast::StmtIf {
+                test,
+                body,
+                elif_else_clauses,
+                ..
+            }) => {
+                record(test, &mut bound, params);
+                let mut branch_bounds = vec![scan_block_bindings(body, &bound, params)];
+                let mut has_else = false;
+                for clause in elif_else_clauses {
+                    if let Some(test) = &clause.test {
+                        let mut clause_bound = bound.clone();
+                        record(test, &mut clause_bound, params);
+                        branch_bounds.push(scan_block_bindings(
+                            &clause.body,
+                            &clause_bound,
+                            params,
+                        ));
+                    } else {
+                        has_else = true;
+                        branch_bounds.push(scan_block_bindings(&clause.body, &bound, params));
+                    }
+                }
+                if has_else {
+                    // Only a name bound on *every* branch is guaranteed bound afterwards.
+                    let mut intersection = branch_bounds[0].clone();
+                    for branch_bound in &branch_bounds[1..] {
+                        intersection.retain(|bound_name| branch_bound.contains(bound_name));
+                    }
+                    bound = intersection;
+                }
+            }
+            Stmt::For(ast::StmtFor {
+                target,
+                iter,
+                body,
+                orelse,
+                ..
+            }) => {
+                record(iter, &mut bound, params);
+                let mut body_bound = bound.clone();
+                record(target, &mut body_bound, params);
+                scan_block_bindings(body, &body_bound, params);
+                bound = scan_block_bindings(orelse, &bound, params);
+            }
+            Stmt::While(ast::StmtWhile {
+                test, body, orelse, ..
+            }) => {
+                record(test, &mut bound, params);
+                scan_block_bindings(body, &bound, params);
+                bound = scan_block_bindings(orelse, &bound, params);
+            }
+            Stmt::With(ast::StmtWith { items, body, .. }) => {
+                for item in items {
+                    record(&item.context_expr, &mut bound, params);
+                    if let Some(target) = &item.optional_vars {
+                        record(target, &mut bound, params);
+                    }
+                }
+                bound = scan_block_bindings(body, &bound, params);
+            }
+            Stmt::Try(ast::StmtTry {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+                ..
+            }) => {
+                scan_block_bindings(body, &bound, params);
+                for handler in handlers {
+                    let ExceptHandler::ExceptHandler(ast::ExceptHandlerExceptHandler {
+                        body, ..
+                    }) = handler;
+                    scan_block_bindings(body, &bound, params);
+                }
+                bound = scan_block_bindings(orelse, &bound, params);
+                bound = scan_block_bindings(finalbody, &bound, params);
+            }
+            Stmt::Match(ast::StmtMatch { subject, cases, .. }) => {
+                record(subject, &mut bound, params);
+                for case in cases {
+                    let mut case_bound = bound.clone();
+                    if let Some(guard) = &case.guard {
+                        record(guard, &mut case_bound, params);
+                    }
+                    scan_block_bindings(&case.body, &case_bound, params);
+                }
+            }
+            _ => {}
+        }
+    }
+    bound
+}
+
+/// Generate a helper function name derived from `name` that doesn't collide with
+/// any identifier in `existing_names`, appending a numeric suffix until it's unique.
+fn fresh_function_name(name: &str, existing_names: &IndexSet<String>) -> String {
+    let base = format!("_{name}_extracted");
+    if !existing_names.contains(&base) {
+        return base;
+    }
+    let mut counter = 2;
+    loop {
+        let candidate = format!("{base}_{counter}");
+        if !existing_names.contains(&candidate) {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// Generate an "extract block" fix for a too-complex function: pull the single
+/// most-nested contiguous block out into a new top-level helper function, and
+/// replace the original block with a call to it.
+///
+/// The free variables read within the block become parameters; any variables the
+/// block writes to (and that the rest of the function may still need) are returned
+/// and reassigned at the call site. This is an unsafe fix: it can't always prove
+/// that every write it returns is actually read afterwards, or that the extracted
+/// name doesn't shadow something meaningful.
+fn extract_block_fix(stmt: &Stmt, name: &str, body: &[Stmt], locator: &Locator) -> Option<Fix> {
+    let (_, block) = find_most_nested_block(body, 0)?;
+    if block_has_unsupported_control_flow(block) {
+        // A `return`/`break`/`continue`/`yield` inside the block is part of the
+        // enclosing function's control flow; moving it into a standalone helper
+        // whose result is discarded at the call site would silently change what
+        // the function does.
+        return None;
+    }
+    let (first, last) = (block.first()?, block.last()?);
+    let block_range = TextRange::new(first.start(), last.end());
+
+    let mut params = IndexSet::default();
+    scan_block_bindings(block, &IndexSet::default(), &mut params);
+
+    let mut writes = IndexSet::default();
+    let mut _block_reads = IndexSet::default();
+    collect_block_name_refs(block, &mut _block_reads, &mut writes);
+    let params: Vec<&String> = params.iter().collect();
+    let returns: Vec<&String> = writes.iter().collect();
+
+    let mut function_reads = IndexSet::default();
+    let mut function_writes = IndexSet::default();
+    collect_block_name_refs(body, &mut function_reads, &mut function_writes);
+    let existing_names: IndexSet<String> =
+        function_reads.union(&function_writes).cloned().collect();
+    let new_name = fresh_function_name(name, &existing_names);
+
+    let is_async = block_awaits(block);
+    let async_keyword = if is_async { "async " } else { "" };
+    let await_keyword = if is_async { "await " } else { "" };
+
+    let params_str = params
+        .iter()
+        .map(|p| p.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let returns_str = returns
+        .iter()
+        .map(|r| r.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let block_source = locator.slice(block_range);
+    let helper_body = reindent(block_source, "    ");
+
+    let mut new_function = format!("{async_keyword}def {new_name}({params_str}):\n{helper_body}");
+    if !returns.is_empty() {
+        new_function.push_str(&format!("\n    return {returns_str}"));
+    }
+    new_function.push_str("\n\n\n");
+
+    let indent = locator.slice(TextRange::new(
+        locator.line_start(first.start()),
+        first.start(),
+    ));
+    let call_expr = format!("{await_keyword}{new_name}({params_str})");
+    let call_stmt = if returns.is_empty() {
+        format!("{indent}{call_expr}")
+    } else {
+        format!("{indent}{returns_str} = {call_expr}")
+    };
+
+    Some(Fix::unsafe_edits(
+        Edit::insertion(new_function, locator.line_start(stmt.start())),
+        [Edit::range_replacement(call_stmt, block_range)],
+    ))
+}
+
+/// ## What it does
+/// Checks for functions with a high cognitive complexity.
+#[derive(ViolationMetadata)]
+pub(crate) struct CognitiveComplexity {
+    name: String,
+    complexity: usize,
+    max_complexity: usize,
+}
+
+impl Violation for CognitiveComplexity {
+    #[derive_message_formats]
+    fn message(&self) -> String {
+        let CognitiveComplexity {
+            name,
+            complexity,
+            max_complexity,
+        } = self;
+        format!("`{name}` is too complex ({complexity} > {max_complexity})")
+    }
+}
+
+/// Returns `true` if `expr` is a direct call to the function named `name`.
+fn is_call_to(expr: &Expr, name: &str) -> bool {
+    let Expr::Call(ast::ExprCall { func, .. }) = expr else {
+        return false;
+    };
+    matches!(func.as_ref(), Expr::Name(ast::ExprName { id, .. }) if id.as_str() == name)
+}
+
+/// Count calls to `name` appearing anywhere within `expr`, descending through every
+/// expression kind `collect_name_refs` does (comprehensions, lambdas,
+/// `await`/`yield`, subscripts, f-strings, and so on) rather than a second, narrower
+/// traversal that would silently miss recursive calls nested inside them. Used to
+/// detect direct recursion from within the enclosing function's own body; only calls
+/// that name the function itself are counted, so recursion that goes through another
+/// function is not detected.
+fn count_recursive_calls(expr: &Expr, name: &str) -> usize {
+    let mut count = usize::from(is_call_to(expr, name));
+    match expr {
+        Expr::Call(ast::ExprCall {
+            func, arguments, ..
+        }) => {
+            count += count_recursive_calls(func, name);
+            for arg in &arguments.args {
+                count += count_recursive_calls(arg, name);
+            }
+            for keyword in &arguments.keywords {
+                count += count_recursive_calls(&keyword.value, name);
+            }
+        }
+        Expr::BoolOp(ast::ExprBoolOp { values, .. }) => {
+            for value in values {
+                count += count_recursive_calls(value, name);
+            }
+        }
+        Expr::BinOp(ast::ExprBinOp { left, right, .. }) => {
+            count += count_recursive_calls(left, name);
+            count += count_recursive_calls(right, name);
+        }
+        Expr::UnaryOp(ast::ExprUnaryOp { operand, .. }) => {
+            count += count_recursive_calls(operand, name);
+        }
+        Expr::Compare(ast::ExprCompare {
+            left, comparators, ..
+        }) => {
+            count += count_recursive_calls(left, name);
+            for comparator in comparators {
+                count += count_recursive_calls(comparator, name);
+            }
+        }
+        Expr::IfExp(ast::ExprIfExp {
+            test, body, orelse, ..
+        }) => {
+            count += count_recursive_calls(test, name);
+            count += count_recursive_calls(body, name);
+            count += count_recursive_calls(orelse, name);
+        }
+        Expr::Attribute(ast::ExprAttribute { value, .. }) => {
+            count += count_recursive_calls(value, name);
+        }
+        Expr::Subscript(ast::ExprSubscript { value, slice, .. }) => {
+            count += count_recursive_calls(value, name);
+            count += count_recursive_calls(slice, name);
+        }
+        Expr::Starred(ast::ExprStarred { value, .. })
+        | Expr::Await(ast::ExprAwait { value, .. })
+        | Expr::YieldFrom(ast::ExprYieldFrom { value, .. })
+        | Expr::Named(ast::ExprNamed { value, .. }) => {
+            count += count_recursive_calls(value, name);
+        }
+        Expr::Yield(ast::ExprYield { value, .. }) => {
+            if let Some(value) = value {
+                count += count_recursive_calls(value, name);
+            }
+        }
+        Expr::Tuple(ast::ExprTuple { elts, .. })
+        | Expr::List(ast::ExprList { elts, .. })
+        | Expr::Set(ast::ExprSet { elts, .. }) => {
+            for elt in elts {
+                count += count_recursive_calls(elt, name);
+            }
+        }
+        Expr::Dict(ast::ExprDict { items, .. }) => {
+            for item in items {
+                if let Some(key) = &item.key {
+                    count += count_recursive_calls(key, name);
+                }
+                count += count_recursive_calls(&item.value, name);
+            }
+        }
+        Expr::Slice(ast::ExprSlice {
+            lower, upper, step, ..
+        }) => {
+            for part in [lower, upper, step].into_iter().flatten() {
+                count += count_recursive_calls(part, name);
             }
         }
-        
-        count
+        Expr::FString(ast::ExprFString { value, .. }) => {
+            for part in value {
+                if let ast::FStringPart::FString(f_string) = part {
+                    for element in &f_string.elements {
+                        if let ast::FStringElement::Expression(expression) = element {
+                            count += count_recursive_calls(&expression.expression, name);
+                        }
+                    }
+                }
+            }
+        }
+        Expr::Lambda(ast::ExprLambda { body, .. }) => {
+            count += count_recursive_calls(body, name);
+        }
+        Expr::ListComp(ast::ExprListComp {
+            elt, generators, ..
+        })
+        | Expr::SetComp(ast::ExprSetComp {
+            elt, generators, ..
+        })
+        | Expr::GeneratorExp(ast::ExprGenerator {
+            elt, generators, ..
+        }) => {
+            count += count_recursive_calls(elt, name);
+            count += count_recursive_calls_in_generators(generators, name);
+        }
+        Expr::DictComp(ast::ExprDictComp {
+            key,
+            value,
+            generators,
+            ..
+        }) => {
+            count += count_recursive_calls(key, name);
+            count += count_recursive_calls(value, name);
+            count += count_recursive_calls_in_generators(generators, name);
+        }
+        _ => {}
+    }
+    count
+}
+
+/// Count calls to `name` within a comprehension's `iter` and `if` clauses; the bound
+/// target itself can't reference a call, so only those two parts are walked.
+fn count_recursive_calls_in_generators(generators: &[ast::Comprehension], name: &str) -> usize {
+    generators
+        .iter()
+        .map(|generator| {
+            count_recursive_calls(&generator.iter, name)
+                + generator
+                    .ifs
+                    .iter()
+                    .map(|if_clause| count_recursive_calls(if_clause, name))
+                    .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Get the cognitive-complexity contribution of `expr` when it is itself a
+/// boolean-operator sequence or a conditional (ternary) expression. Such expressions
+/// nested inside other expression kinds (calls, arithmetic, comparisons, and so on)
+/// are not counted.
+fn get_cognitive_expression_complexity(expr: &Expr, nesting: usize) -> usize {
+    match expr {
+        Expr::BoolOp(_) => get_expression_complexity(expr),
+        Expr::IfExp(ast::ExprIfExp {
+            test, body, orelse, ..
+        }) => {
+            1 + nesting
+                + get_cognitive_expression_complexity(test, nesting)
+                + get_cognitive_expression_complexity(body, nesting)
+                + get_cognitive_expression_complexity(orelse, nesting)
+        }
+        _ => 0,
+    }
+}
+
+/// Get the cognitive-complexity contribution of an expression that appears as the
+/// value of a statement (e.g. a `return` value or an assignment's right-hand side),
+/// including any recursive calls back into the enclosing function.
+fn get_cognitive_value_complexity(
+    expr: &Expr,
+    nesting: usize,
+    enclosing_name: Option<&str>,
+) -> usize {
+    let mut complexity = get_cognitive_expression_complexity(expr, nesting);
+    if let Some(name) = enclosing_name {
+        complexity += count_recursive_calls(expr, name);
     }
-    
-    count_bool_op_sequences(expr, None, 0)
+    complexity
 }
 
-fn get_complexity_number(stmts: &[Stmt]) -> usize {
+/// Get the cognitive complexity of a sequence of statements, per the
+/// Campbell/SonarSource algorithm: every `if`, `for`, `while`, `match`, and `except`
+/// handler adds `1 + nesting` and recurses into its body at `nesting + 1`, while
+/// `elif`/`else` clauses (and the `else` of a loop) add a flat `+1` and recurse at
+/// the same nesting level as their owning `if`/loop.
+fn get_cognitive_complexity(stmts: &[Stmt], nesting: usize, enclosing_name: Option<&str>) -> usize {
     let mut complexity = 0;
     for stmt in stmts {
         match stmt {
@@ -59,57 +1269,55 @@ fn get_complexity_number(stmts: &[Stmt]) -> usize {
                 elif_else_clauses,
                 ..
             }) => {
-                complexity += 1;
-                complexity += get_expression_complexity(test);
-                complexity += get_complexity_number(body);
-                
+                complexity += 1 + nesting;
+                complexity += get_cognitive_value_complexity(test, nesting, enclosing_name);
+                complexity += get_cognitive_complexity(body, nesting + 1, enclosing_name);
+
                 for clause in elif_else_clauses {
                     complexity += 1;
-                    
+
                     if let Some(test) = &clause.test {
-                        complexity += get_expression_complexity(test);
+                        complexity += get_cognitive_value_complexity(test, nesting, enclosing_name);
                     }
-                    
-                    complexity += get_complexity_number(&clause.body);
+
+                    complexity +=
+                        get_cognitive_complexity(&clause.body, nesting + 1, enclosing_name);
                 }
             }
             Stmt::For(ast::StmtFor { body, orelse, .. }) => {
-                complexity += 1;
-                complexity += get_complexity_number(body);
+                complexity += 1 + nesting;
+                complexity += get_cognitive_complexity(body, nesting + 1, enclosing_name);
 
                 if !orelse.is_empty() {
                     complexity += 1;
                 }
-
-                complexity += get_complexity_number(orelse);
-            }
-            Stmt::With(ast::StmtWith { body, .. }) => {
-                complexity += get_complexity_number(body);
+                complexity += get_cognitive_complexity(orelse, nesting + 1, enclosing_name);
             }
-            Stmt::While(ast::StmtWhile { test, body, orelse, .. }) => {
-                complexity += 1;
-                complexity += get_expression_complexity(test);
-                complexity += get_complexity_number(body);
+            Stmt::While(ast::StmtWhile {
+                test, body, orelse, ..
+            }) => {
+                complexity += 1 + nesting;
+                complexity += get_cognitive_value_complexity(test, nesting, enclosing_name);
+                complexity += get_cognitive_complexity(body, nesting + 1, enclosing_name);
 
                 if !orelse.is_empty() {
                     complexity += 1;
                 }
-
-                complexity += get_complexity_number(orelse);
+                complexity += get_cognitive_complexity(orelse, nesting + 1, enclosing_name);
             }
-            Stmt::Match(ast::StmtMatch { cases, .. }) => {
-                complexity += 1;
-                
+            Stmt::With(ast::StmtWith { body, .. }) => {
+                complexity += get_cognitive_complexity(body, nesting, enclosing_name);
+            }
+            Stmt::Match(ast::StmtMatch { subject, cases, .. }) => {
+                complexity += 1 + nesting;
+                complexity += get_cognitive_value_complexity(subject, nesting, enclosing_name);
+
                 for case in cases {
-                    if case.pattern.is_irrefutable() {
-                        complexity += 1;
-                    }
-                    
                     if let Some(guard) = &case.guard {
-                        complexity += get_expression_complexity(guard);
+                        complexity +=
+                            get_cognitive_value_complexity(guard, nesting, enclosing_name);
                     }
-                    
-                    complexity += get_complexity_number(&case.body);
+                    complexity += get_cognitive_complexity(&case.body, nesting + 1, enclosing_name);
                 }
             }
             Stmt::Try(ast::StmtTry {
@@ -119,32 +1327,43 @@ fn get_complexity_number(stmts: &[Stmt]) -> usize {
                 finalbody,
                 ..
             }) => {
-                complexity += get_complexity_number(body);
-                
-                if !handlers.is_empty() {
-                    complexity += 1;
-                }
-                
-                if !orelse.is_empty() {
-                    complexity += 1;
-                }
-                
-                // Process the bodies of all handlers and clauses
-                complexity += get_complexity_number(orelse);
-                complexity += get_complexity_number(finalbody);
-                
+                complexity += get_cognitive_complexity(body, nesting, enclosing_name);
+
                 for handler in handlers {
                     let ExceptHandler::ExceptHandler(ast::ExceptHandlerExceptHandler {
                         body, ..
                     }) = handler;
-                    complexity += get_complexity_number(body);
+                    complexity += 1 + nesting;
+                    complexity += get_cognitive_complexity(body, nesting + 1, enclosing_name);
                 }
+
+                complexity += get_cognitive_complexity(orelse, nesting, enclosing_name);
+                complexity += get_cognitive_complexity(finalbody, nesting, enclosing_name);
             }
-            Stmt::FunctionDef(ast::StmtFunctionDef { body, .. }) => {
-                complexity += get_complexity_number(body);
+            Stmt::FunctionDef(ast::StmtFunctionDef { name, body, .. }) => {
+                complexity += get_cognitive_complexity(body, nesting + 1, Some(name.as_str()));
             }
             Stmt::ClassDef(ast::StmtClassDef { body, .. }) => {
-                complexity += get_complexity_number(body);
+                complexity += get_cognitive_complexity(body, nesting, enclosing_name);
+            }
+            Stmt::Return(ast::StmtReturn {
+                value: Some(value), ..
+            }) => {
+                complexity += get_cognitive_value_complexity(value, nesting, enclosing_name);
+            }
+            Stmt::Assign(ast::StmtAssign { value, .. }) => {
+                complexity += get_cognitive_value_complexity(value, nesting, enclosing_name);
+            }
+            Stmt::AugAssign(ast::StmtAugAssign { value, .. }) => {
+                complexity += get_cognitive_value_complexity(value, nesting, enclosing_name);
+            }
+            Stmt::AnnAssign(ast::StmtAnnAssign {
+                value: Some(value), ..
+            }) => {
+                complexity += get_cognitive_value_complexity(value, nesting, enclosing_name);
+            }
+            Stmt::Expr(ast::StmtExpr { value, .. }) => {
+                complexity += get_cognitive_value_complexity(value, nesting, enclosing_name);
             }
             _ => {}
         }
@@ -152,19 +1371,19 @@ fn get_complexity_number(stmts: &[Stmt]) -> usize {
     complexity
 }
 
-pub(crate) fn function_is_too_complex(
+pub(crate) fn function_is_too_cognitively_complex(
     stmt: &Stmt,
     name: &str,
     body: &[Stmt],
-    max_complexity: usize,
+    max_cognitive_complexity: usize,
 ) -> Option<Diagnostic> {
-    let complexity = get_complexity_number(body) + 1;
-    if complexity > max_complexity {
+    let complexity = get_cognitive_complexity(body, 0, Some(name));
+    if complexity > max_cognitive_complexity {
         Some(Diagnostic::new(
-            ComplexStructure {
+            CognitiveComplexity {
                 name: name.to_string(),
                 complexity,
-                max_complexity,
+                max_complexity: max_cognitive_complexity,
             },
             stmt.range(),
         ))
@@ -177,15 +1396,27 @@ pub(crate) fn function_is_too_complex(
 mod tests {
     use anyhow::Result;
 
-    use ruff_python_ast::Suite;
+    use ruff_python_ast::{self as ast, Stmt, Suite};
     use ruff_python_parser::parse_module;
+    use ruff_source_file::Locator;
 
-    use super::get_complexity_number;
+    use super::{
+        extract_block_fix, get_cognitive_complexity, get_complexity_number, MatchComplexityStrategy,
+    };
 
     fn parse_suite(source: &str) -> Result<Suite> {
         Ok(parse_module(source)?.into_suite())
     }
 
+    /// Extract the lone top-level function definition's `(stmt, name, body)` triple,
+    /// as passed to `extract_block_fix` by `function_is_too_complex`.
+    fn get_function(stmts: &Suite) -> (&Stmt, &str, &[Stmt]) {
+        let Stmt::FunctionDef(ast::StmtFunctionDef { name, body, .. }) = &stmts[0] else {
+            panic!("expected a function definition");
+        };
+        (&stmts[0], name.as_str(), body.as_slice())
+    }
+
     #[test]
     fn trivial() -> Result<()> {
         let source = r"
@@ -193,7 +1424,10 @@ def trivial():
     pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -204,7 +1438,10 @@ def expr_as_statement():
     0xF00D
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -217,7 +1454,10 @@ def sequential(n):
     return s
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -233,7 +1473,10 @@ def if_elif_else_dead_path(n):
         return "smaller than or equal to three"
 "#;
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 3);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            3
+        );
         Ok(())
     }
 
@@ -250,7 +1493,10 @@ def nested_ifs():
         return "smaller than or equal to three"
 "#;
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 4);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            4
+        );
         Ok(())
     }
 
@@ -262,7 +1508,10 @@ def for_loop():
         print(i)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 1);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
         Ok(())
     }
 
@@ -276,7 +1525,10 @@ def for_else(mylist):
         print(None)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -290,7 +1542,10 @@ def recursive(n):
         return n
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -307,7 +1562,10 @@ def nested_functions():
     a()
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -324,7 +1582,10 @@ def nested_try_finally():
         print(3)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -341,7 +1602,10 @@ async def foobar(a, b, c):
         pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -352,7 +1616,10 @@ def annotated_assign():
     x: Any = None
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -375,7 +1642,10 @@ class Class:
         return ServiceProvider(Logger())
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -389,7 +1659,10 @@ def process_detect_lines():
         pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 0);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            0
+        );
         Ok(())
     }
 
@@ -404,7 +1677,10 @@ def process_detect_lines():
             errors.append(f"Non-zero exit code {res}")
 "#;
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 1);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
         Ok(())
     }
 
@@ -417,7 +1693,10 @@ def with_lock():
             print('bar')
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 1);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
         Ok(())
     }
 
@@ -432,7 +1711,10 @@ def f():
             print('bar')
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -451,7 +1733,10 @@ def f():
             print('baz')
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -466,7 +1751,10 @@ def f():
             print(x)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -477,11 +1765,84 @@ def f():
     match subject:          # +1
         case 2:
             print('hello')
-        case 5 | _:         # +1 for _
+        case 5 | _:         # +1 for _, +1 for the | alternation
             print(x)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn match_pattern_alternation() -> Result<()> {
+        let source = r"
+def f():
+    match subject:        # +1
+        case 1 | 2 | 3:    # +2 for the two extra alternatives
+            pass
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn match_per_arm_counts_every_case() -> Result<()> {
+        let source = r"
+def f():
+    match subject:   # +1
+        case 2:      # +1
+            pass
+        case 3:      # +1
+            pass
+        case _:      # trailing wildcard, no guard: +0
+            pass
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::PerArm),
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn match_per_arm_named_capture_is_not_a_wildcard() -> Result<()> {
+        let source = r"
+def f():
+    match subject:   # +1
+        case 2:      # +1
+            pass
+        case x:      # named capture, not `_`: +1
+            pass
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::PerArm),
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn match_per_arm_guarded_wildcard_counts() -> Result<()> {
+        let source = r"
+def f():
+    match subject:     # +1
+        case _ if a:   # guarded, so not skipped: +1 (the guard itself, `a`, is a bare
+            pass       # name and contributes nothing on its own)
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::PerArm),
+            2
+        );
         Ok(())
     }
 
@@ -495,7 +1856,10 @@ class C:
             pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -509,9 +1873,12 @@ class C:
             pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 3);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            3
+        );
         Ok(())
-    }    
+    }
 
     #[test]
     fn counts_for_each_new_logical_expression_sequence_even_if_it_was_used_before() -> Result<()> {
@@ -523,7 +1890,10 @@ class C:
             pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 4);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            4
+        );
         Ok(())
     }
 
@@ -535,9 +1905,12 @@ def test_while():
         pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
-    } 
+    }
 
     #[test]
     fn match_with_guard_logical_expression() -> Result<()> {
@@ -548,7 +1921,10 @@ def test_match(value):
             pass
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 3);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            3
+        );
         Ok(())
     }
 
@@ -562,7 +1938,10 @@ def while_else(condition):
         print('in else')
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
         Ok(())
     }
 
@@ -580,7 +1959,305 @@ def try_else():
         print(4)
 ";
         let stmts = parse_suite(source)?;
-        assert_eq!(get_complexity_number(&stmts), 2);
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ternary_expression() -> Result<()> {
+        let source = r"
+def ternary(n):
+    return 1 if n else 2   # +1
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn comprehension_guard() -> Result<()> {
+        let source = r"
+def comprehension_guard(xs):
+    return [x for x in xs if x > 0]   # +1 for the comprehension if
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn comprehension_guard_with_bool_op() -> Result<()> {
+        let source = r"
+def comprehension_guard_bool_op(xs):
+    return [x for x in xs if x > 0 and x < 10]   # +1 for the if, +1 for the and
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            2
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lambda_with_bool_op() -> Result<()> {
+        let source = r"
+def lambda_with_bool_op():
+    f = lambda x, y: x and y   # +1 for the and
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn ternary_in_call_argument() -> Result<()> {
+        let source = r"
+def ternary_in_call_argument(n):
+    print(1 if n else 2)   # +1, found inside a call argument
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(
+            get_complexity_number(&stmts, MatchComplexityStrategy::Legacy),
+            1
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_trivial() -> Result<()> {
+        let source = r"
+def trivial():
+    pass
+";
+        let stmts = parse_suite(source)?;
+        assert_eq!(get_cognitive_complexity(&stmts, 0, Some("trivial")), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_nested_ifs() -> Result<()> {
+        let source = r"
+def nested_ifs(n):
+    if n > 3:        # +1 (nesting 0)
+        if n > 4:    # +2 (nesting 1)
+            return 1
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_deeply_nested_if_in_loops() -> Result<()> {
+        let source = r"
+def deeply_nested(xs):
+    for x in xs:            # +1 (nesting 0)
+        for y in x:          # +2 (nesting 1)
+            if y:             # +3 (nesting 2)
+                pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_elif_else_flat() -> Result<()> {
+        let source = r"
+def elif_else(n):
+    if n > 3:       # +1
+        pass
+    elif n > 2:     # +1, flat
+        pass
+    else:           # +1, flat
+        pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_bool_op_is_flat() -> Result<()> {
+        let source = r"
+def bool_op(a, b, c):
+    if a and b and c:   # +1 for if, +1 for the and-run (flat, no nesting bonus)
+        pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_ternary() -> Result<()> {
+        let source = r"
+def ternary(n):
+    return 1 if n else 2   # +1
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 1);
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn cognitive_recursive_call() -> Result<()> {
+        let source = r"
+def factorial(n):
+    if n <= 1:               # +1
+        return 1
+    return n * factorial(n - 1)  # +1 for the recursive call
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_nested_function_increments_nesting() -> Result<()> {
+        let source = r"
+def outer():
+    def inner(n):
+        if n:    # +2, nested one level inside `outer`
+            pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_except_handler() -> Result<()> {
+        let source = r"
+def f():
+    try:
+        pass
+    except ValueError:   # +1
+        pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn cognitive_match_with_guard() -> Result<()> {
+        let source = r"
+def f(value):
+    match value:                 # +1
+        case 1 if a and b:       # +1 for the and-run (flat)
+            pass
+";
+        let stmts = parse_suite(source)?;
+        let (_, name, body) = get_function(&stmts);
+        assert_eq!(get_cognitive_complexity(body, 0, Some(name)), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn extract_block_accumulator_is_param_and_return() -> Result<()> {
+        let source = r"
+def total_of(items):
+    if True:
+        for item in items:
+            total = total + item
+    return total
+";
+        let stmts = parse_suite(source)?;
+        let (stmt, name, body) = get_function(&stmts);
+        let locator = Locator::new(source);
+        let fix = extract_block_fix(stmt, name, body, &locator).expect("expected a fix");
+        let new_function = fix.edits()[0].content().expect("insertion has content");
+        // `total` is read (`total + item`) before this block ever assigns it, so the
+        // caller's binding has to come in as a parameter, not just fall out of the
+        // naive `reads - writes` set difference.
+        assert!(
+            new_function.contains("def _total_of_extracted(total, item):"),
+            "unexpected signature in: {new_function}"
+        );
+        assert!(new_function.contains("return total"));
+        Ok(())
+    }
+
+    #[test]
+    fn extract_block_bails_on_return_in_block() -> Result<()> {
+        let source = r"
+def f(items):
+    for item in items:
+        if item:
+            return item
+        print(item)
+";
+        let stmts = parse_suite(source)?;
+        let (stmt, name, body) = get_function(&stmts);
+        let locator = Locator::new(source);
+        assert!(extract_block_fix(stmt, name, body, &locator).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn extract_block_preserves_async() -> Result<()> {
+        let source = r"
+async def f(items):
+    if True:
+        for item in items:
+            await item
+";
+        let stmts = parse_suite(source)?;
+        let (stmt, name, body) = get_function(&stmts);
+        let locator = Locator::new(source);
+        let fix = extract_block_fix(stmt, name, body, &locator).expect("expected a fix");
+        let new_function = fix.edits()[0].content().expect("insertion has content");
+        assert!(new_function.starts_with("async def _f_extracted(item):"));
+        let call_site = fix.edits()[1].content().expect("replacement has content");
+        assert!(call_site
+            .trim_start()
+            .starts_with("await _f_extracted(item)"));
+        Ok(())
+    }
+
+    #[test]
+    fn extract_block_picks_fresh_name_on_collision() -> Result<()> {
+        let source = r"
+def f(items):
+    def _f_extracted():
+        pass
+    if True:
+        for item in items:
+            item
+";
+        let stmts = parse_suite(source)?;
+        let (stmt, name, body) = get_function(&stmts);
+        let locator = Locator::new(source);
+        let fix = extract_block_fix(stmt, name, body, &locator).expect("expected a fix");
+        let new_function = fix.edits()[0].content().expect("insertion has content");
+        // `_f_extracted` is already taken by the nested `def` above, so the helper
+        // must pick the next free name instead of colliding with it.
+        assert!(
+            new_function.contains("def _f_extracted_2(item):"),
+            "unexpected signature in: {new_function}"
+        );
+        Ok(())
+    }
+}